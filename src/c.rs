@@ -1,14 +1,222 @@
 pub mod crs {
-    use std::collections::HashMap;
+    use std::collections::BTreeMap;
+    use std::error::Error;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::path::Path;
 
     pub type CRS = String;
 
-    pub fn crs(code: &CRS) -> &'static str {
-        let db = HashMap::from([
-            ("EDB".to_string(), "Edinburgh Waverley"),
-            ("BHG".to_string(), "Bathgate")
-        ]);
+    #[derive(Debug, Clone, Default)]
+    pub struct Stop {
+        pub name: String,
+        pub lat: f64,
+        pub lon: f64
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct StopTime {
+        trip_id: String,
+        departure_time: String
+    }
+
+    /// Lookup tables built from a GTFS feed (`stops.txt`, `routes.txt`, `trips.txt`,
+    /// `stop_times.txt`), used to resolve a CRS/stop code to a station and, where a
+    /// matching trip exists, its next scheduled departure.
+    #[derive(Debug, Clone, Default)]
+    pub struct Gtfs {
+        stops: BTreeMap<CRS, Stop>,
+        trip_routes: BTreeMap<String, String>,
+        route_names: BTreeMap<String, String>,
+        departures_by_stop: BTreeMap<CRS, Vec<StopTime>>
+    }
+
+    /// Splits a single GTFS CSV line on commas, honouring double-quoted fields that may
+    /// themselves contain commas.
+    fn split_csv_line(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+
+        for c in line.chars() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ',' if !in_quotes => {
+                    fields.push(field.trim().to_string());
+                    field = String::new();
+                },
+                _ => field.push(c)
+            }
+        }
+        fields.push(field.trim().to_string());
+
+        fields
+    }
+
+    /// Streams a GTFS CSV file line by line via `BufReader`, calling `f` with the header
+    /// column names and each record's fields, so large national feeds don't blow memory.
+    fn for_each_record<F: FnMut(&[String], &[String])>(path: &Path, mut f: F) -> Result<(), Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(());
+        }
+
+        let file = File::open(path)?;
+        let mut lines = BufReader::new(file).lines();
+
+        let header = match lines.next() {
+            Some(header) => split_csv_line(&header?),
+            None => return Ok(())
+        };
+
+        for line in lines {
+            let record = split_csv_line(&line?);
+            f(&header, &record);
+        }
+
+        Ok(())
+    }
+
+    fn column<'a>(header: &[String], record: &'a [String], name: &str) -> Option<&'a str> {
+        header
+            .iter()
+            .position(|h| h == name)
+            .and_then(|i| record.get(i))
+            .map(|s| s.as_str())
+    }
+
+    impl Gtfs {
+        /// Loads `stops.txt`, `routes.txt`, `trips.txt` and `stop_times.txt` from `dir`.
+        /// Files that don't exist are skipped, so a partial feed (or none at all) still
+        /// yields a usable (if empty) set of lookup tables.
+        pub fn load(dir: &str) -> Result<Gtfs, Box<dyn Error>> {
+            let mut gtfs = Gtfs::default();
+            let dir = Path::new(dir);
+
+            for_each_record(&dir.join("stops.txt"), |header, record| {
+                let (Some(id), Some(name)) = (
+                    column(header, record, "stop_id"),
+                    column(header, record, "stop_name")
+                ) else { return };
+
+                let lat = column(header, record, "stop_lat").and_then(|v| v.parse().ok()).unwrap_or(0.0);
+                let lon = column(header, record, "stop_lon").and_then(|v| v.parse().ok()).unwrap_or(0.0);
+
+                gtfs.stops.insert(id.to_string(), Stop { name: name.to_string(), lat, lon });
+            })?;
+
+            for_each_record(&dir.join("routes.txt"), |header, record| {
+                let (Some(id), Some(short_name)) = (
+                    column(header, record, "route_id"),
+                    column(header, record, "route_short_name")
+                ) else { return };
+
+                gtfs.route_names.insert(id.to_string(), short_name.to_string());
+            })?;
+
+            for_each_record(&dir.join("trips.txt"), |header, record| {
+                let (Some(trip_id), Some(route_id)) = (
+                    column(header, record, "trip_id"),
+                    column(header, record, "route_id")
+                ) else { return };
+
+                gtfs.trip_routes.insert(trip_id.to_string(), route_id.to_string());
+            })?;
+
+            for_each_record(&dir.join("stop_times.txt"), |header, record| {
+                let (Some(trip_id), Some(stop_id), Some(departure_time)) = (
+                    column(header, record, "trip_id"),
+                    column(header, record, "stop_id"),
+                    column(header, record, "departure_time")
+                ) else { return };
+
+                gtfs.departures_by_stop
+                    .entry(stop_id.to_string())
+                    .or_default()
+                    .push(StopTime { trip_id: trip_id.to_string(), departure_time: departure_time.to_string() });
+            })?;
+
+            for departures in gtfs.departures_by_stop.values_mut() {
+                departures.sort_by(|a, b| a.departure_time.cmp(&b.departure_time));
+            }
+
+            Ok(gtfs)
+        }
+
+        /// Resolves a CRS/stop code to its station, replacing the old panicking `db[code]`.
+        pub fn resolve(&self, code: &str) -> Option<&Stop> {
+            self.stops.get(code)
+        }
+
+        /// Returns the next scheduled departure at `code` at or after `after` (`"HH:MM:SS"`),
+        /// along with the route's short name, if a matching trip exists. When nothing departs
+        /// at or after `after`, wraps around to the stop's first departure of the day.
+        pub fn next_departure(&self, code: &str, after: &str) -> Option<(&str, &str)> {
+            let departures = self.departures_by_stop.get(code)?;
+
+            let next = departures
+                .iter()
+                .find(|d| d.departure_time.as_str() >= after)
+                .or_else(|| departures.first())?;
+
+            let route_id = self.trip_routes.get(&next.trip_id)?;
+            let route_name = self.route_names.get(route_id)?;
+
+            Some((next.departure_time.as_str(), route_name.as_str()))
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn split_csv_line_honours_quoted_commas() {
+            let fields = split_csv_line(r#"1001,"Station, Central",51.5,0.1"#);
+
+            assert_eq!(fields, vec!["1001", "Station, Central", "51.5", "0.1"]);
+        }
+
+        #[test]
+        fn split_csv_line_trims_unquoted_whitespace() {
+            let fields = split_csv_line("1001, Central , 51.5");
+
+            assert_eq!(fields, vec!["1001", "Central", "51.5"]);
+        }
+
+        fn gtfs_with_departures(stop_id: &str, times: &[&str]) -> Gtfs {
+            let mut gtfs = Gtfs::default();
+
+            gtfs.trip_routes.insert("t1".to_string(), "r1".to_string());
+            gtfs.route_names.insert("r1".to_string(), "Northbound".to_string());
+
+            gtfs.departures_by_stop.insert(
+                stop_id.to_string(),
+                times.iter().map(|t| StopTime { trip_id: "t1".to_string(), departure_time: t.to_string() }).collect()
+            );
+
+            gtfs
+        }
+
+        #[test]
+        fn next_departure_finds_the_first_departure_at_or_after() {
+            let gtfs = gtfs_with_departures("S1", &["08:00:00", "12:00:00", "18:00:00"]);
+
+            assert_eq!(gtfs.next_departure("S1", "09:00:00"), Some(("12:00:00", "Northbound")));
+        }
+
+        #[test]
+        fn next_departure_wraps_around_to_the_first_departure_of_the_day() {
+            let gtfs = gtfs_with_departures("S1", &["08:00:00", "12:00:00", "18:00:00"]);
+
+            // Nothing departs at/after 23:00, so the next service is tomorrow's first train.
+            assert_eq!(gtfs.next_departure("S1", "23:00:00"), Some(("08:00:00", "Northbound")));
+        }
+
+        #[test]
+        fn next_departure_is_none_for_an_unknown_stop() {
+            let gtfs = gtfs_with_departures("S1", &["08:00:00"]);
 
-        db[code]
+            assert_eq!(gtfs.next_departure("S2", "00:00:00"), None);
+        }
     }
 }
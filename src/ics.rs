@@ -0,0 +1,264 @@
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Write};
+
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+
+use crate::rrule::iter_rule;
+use crate::{forward_horizon, parse_date_range, Calendar, DateRange, Event, Transit};
+
+/// Escapes text per RFC 5545 section 3.3.11 (COMMA, SEMICOLON, BACKSLASH, newline).
+fn escape_text(s: &str) -> String {
+    s.chars().fold(String::with_capacity(s.len()), |mut out, c| {
+        match c {
+            ',' | ';' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c)
+        }
+        out
+    })
+}
+
+/// Folds a single logical content line onto multiple physical lines at 75 octets,
+/// each continuation prefixed with a single space, per RFC 5545 section 3.1.
+fn fold_line(line: &str) -> String {
+    let bytes = line.as_bytes();
+
+    if bytes.len() <= 75 {
+        return line.to_string();
+    }
+
+    let mut out = String::with_capacity(line.len() + line.len() / 75 * 3);
+    let mut start = 0;
+
+    while start < bytes.len() {
+        // The first physical line gets the full 75 octets; every continuation's leading
+        // space counts against its budget, so its content is capped at 74.
+        let budget = if start == 0 { 75 } else { 74 };
+        let limit = (start + budget).min(bytes.len());
+
+        let mut end = limit;
+        while end > start && !line.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        if start > 0 {
+            out.push_str("\r\n ");
+        }
+        out.push_str(&line[start..end]);
+
+        start = end;
+    }
+
+    out
+}
+
+fn uid_for(date_range: &DateRange, index: usize, event: &Event) -> String {
+    let mut hasher = DefaultHasher::new();
+
+    date_range.hash(&mut hasher);
+    index.hash(&mut hasher);
+    format!("{:?}", event).hash(&mut hasher);
+
+    format!("{:016x}@calendar.invalid", hasher.finish())
+}
+
+fn ymd(date: NaiveDate) -> String {
+    format!("{:04}{:02}{:02}", date.year(), date.month(), date.day())
+}
+
+fn push_property(out: &mut String, property: &str) {
+    out.push_str(&fold_line(property));
+    out.push_str("\r\n");
+}
+
+fn push_vevent(out: &mut String, uid: &str, dtstamp: &str, lines: &[String]) {
+    push_property(out, "BEGIN:VEVENT");
+    push_property(out, &format!("UID:{uid}"));
+    push_property(out, &format!("DTSTAMP:{dtstamp}"));
+
+    for line in lines {
+        push_property(out, line);
+    }
+
+    push_property(out, "END:VEVENT");
+}
+
+fn transit_summary(transit: &Transit) -> String {
+    let (mode, from, to) = match transit {
+        Transit::Walk { from, to } => ("Walk", from, to),
+        Transit::Bus { from, to, .. } => ("Bus", from, to),
+        Transit::Metro { from, to, .. } => ("Metro", from, to),
+        Transit::Train { from, to, .. } => ("Train", from, to),
+        Transit::Plane { from, to } => ("Plane", from, to)
+    };
+
+    format!("{mode}: {from} to {to}")
+}
+
+fn transit_location(transit: &Transit) -> String {
+    let (from, to) = match transit {
+        Transit::Walk { from, to } => (from, to),
+        Transit::Bus { from, to, .. } => (from, to),
+        Transit::Metro { from, to, .. } => (from, to),
+        Transit::Train { from, to, .. } => (from, to),
+        Transit::Plane { from, to } => (from, to)
+    };
+
+    format!("{from} - {to}")
+}
+
+/// Writes every `virtual_date` unrolled from `calendar` as an RFC 5545 `.ics` file so the
+/// generated site can be subscribed to from phones and desktop clients. Birthdays are
+/// emitted once, anchored at the entry's start date, with a yearly `RRULE` so future
+/// occurrences keep showing up without re-running the build; competitions and transit
+/// legs are emitted per unrolled date, mirroring the HTML/JSON output.
+pub fn write_ics(calendar: &Calendar, path: &str, now: DateTime<Utc>) -> Result<(), Box<dyn Error>> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    let dtstamp = format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        now.year(), now.month(), now.day(),
+        now.hour(), now.minute(), now.second()
+    );
+
+    let mut out = String::new();
+
+    push_property(&mut out, "BEGIN:VCALENDAR");
+    push_property(&mut out, "VERSION:2.0");
+    push_property(&mut out, "PRODID:-//RaspBella//calendar//EN");
+    push_property(&mut out, "CALSCALE:GREGORIAN");
+
+    let horizon = forward_horizon(now);
+
+    for (date_range, entry) in calendar {
+        let events = entry.events();
+        let (start, rule) = parse_date_range(date_range, entry, now.date_naive())?;
+
+        // A rule with no COUNT/UNTIL recurs forever; only that open-ended case gets capped
+        // at `horizon`, the same rule the static HTML build follows. Birthdays short-circuit
+        // to their anchor (below) and carry their own RRULE regardless.
+        let unbounded = rule.count.is_none() && rule.until.is_none();
+        let range = iter_rule(start, rule);
+
+        for (virtual_date_index, virtual_date) in range.take_while(|d| !unbounded || *d <= horizon).enumerate() {
+            for (event_index, event) in events.iter().enumerate() {
+                let index = virtual_date_index * events.len() + event_index;
+                let uid = uid_for(date_range, index, event);
+
+                match event {
+                    Event::Birthday(name) => {
+                        if virtual_date_index > 0 {
+                            continue;
+                        }
+
+                        // A single RRULE:FREQ=YEARLY recurs forever, so it can't carry a
+                        // per-year age (that would freeze whatever `now.year()` was at build
+                        // time into every future occurrence) — keep the SUMMARY age-agnostic.
+                        push_vevent(&mut out, &uid, &dtstamp, &[
+                            format!("DTSTART;VALUE=DATE:{}", ymd(start)),
+                            "RRULE:FREQ=YEARLY".to_string(),
+                            format!("SUMMARY:{}", escape_text(&format!("{name}'s Birthday")))
+                        ]);
+                    },
+                    Event::Comp(id) => {
+                        push_vevent(&mut out, &uid, &dtstamp, &[
+                            format!("DTSTART;VALUE=DATE:{}", ymd(virtual_date)),
+                            format!("SUMMARY:{}", escape_text(id)),
+                            format!("URL:https://www.worldcubeassociation.org/competitions/{id}")
+                        ]);
+                    },
+                    Event::Transit(transit) => {
+                        push_vevent(&mut out, &uid, &dtstamp, &[
+                            format!("DTSTART;VALUE=DATE:{}", ymd(virtual_date)),
+                            format!("SUMMARY:{}", escape_text(&transit_summary(transit))),
+                            format!("LOCATION:{}", escape_text(&transit_location(transit)))
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    push_property(&mut out, "END:VCALENDAR");
+
+    write!(writer, "{out}")?;
+
+    Ok(())
+}
+
+/// Strips HTML tags from the `<h1>`-wrapped strings `format_birthday`/`format_comp`/
+/// `format_transit` return, for output that isn't going into a web page (the `agenda` listing).
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_text_escapes_comma_semicolon_backslash_and_newline() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+    }
+
+    #[test]
+    fn escape_text_leaves_plain_text_untouched() {
+        assert_eq!(escape_text("plain text"), "plain text");
+    }
+
+    #[test]
+    fn fold_line_leaves_short_lines_untouched() {
+        let line = "SUMMARY:short";
+        assert_eq!(fold_line(line), line);
+    }
+
+    #[test]
+    fn fold_line_wraps_at_75_octets_with_a_leading_space() {
+        // Long enough to need a middle continuation line (not just a short final one), so
+        // a continuation that doesn't budget for its own leading space would show up here.
+        let line = format!("SUMMARY:{}", "x".repeat(200));
+        let folded = fold_line(&line);
+
+        for physical in folded.split("\r\n") {
+            assert!(physical.as_bytes().len() <= 75);
+        }
+        assert!(folded.split("\r\n").skip(1).all(|l| l.starts_with(' ')));
+        assert_eq!(folded.replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn fold_line_splits_on_char_boundaries() {
+        // A multi-byte character landing right on the 75-octet cut must not get sliced
+        // mid-codepoint.
+        let line = format!("SUMMARY:{}{}", "x".repeat(66), "\u{1F600}".repeat(3));
+        let folded = fold_line(&line);
+
+        for physical in folded.split("\r\n") {
+            assert!(std::str::from_utf8(physical.as_bytes()).is_ok());
+        }
+    }
+
+    #[test]
+    fn strip_tags_removes_tags_but_keeps_entities() {
+        assert_eq!(strip_tags("<h1>Name</h1> <p>a &ndash; b</p>"), "Name a &ndash; b");
+    }
+}
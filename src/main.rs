@@ -3,9 +3,19 @@ use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs::{File, create_dir_all};
 use std::io::{BufReader, BufWriter, Write};
-use chrono::{Utc, DateTime, Datelike, NaiveDate, Duration, Months};
+use chrono::{Utc, DateTime, Datelike, Timelike, NaiveDate, Duration, Months};
+use clap::{Parser, Subcommand, ValueEnum};
 use regex::Regex;
 
+mod c;
+mod ics;
+mod rrule;
+mod wca;
+use c::crs::Gtfs;
+use ics::{strip_tags, write_ics};
+use rrule::{iter_rule, parse_rrule, Freq, Rule};
+use wca::CompInfo;
+
 type Name = String;
 type CompID = String;
 
@@ -22,7 +32,6 @@ enum Transit {
 }
 
 type DateRange = String;
-type Calendar = BTreeMap<DateRange, Vec<Event>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -32,63 +41,45 @@ enum Event {
     Transit(Transit)
 }
 
-#[derive(Debug)]
-enum DateStep {
-    Days(i64),
-    Months(u32),
-    Years(u32)
-}
-
-#[derive(Debug)]
-struct DateIter {
-    current: NaiveDate,
-    end: NaiveDate,
-    step: DateStep,
-    finished: bool
+/// A calendar entry's events, optionally paired with an explicit `rrule`. Plain
+/// `[Event, ...]` arrays (the original `events.json` shape) still deserialize fine via
+/// `Legacy`, so old files keep building; new entries can opt into `Rich` to set a
+/// recurrence that the date-range key's `+offset` syntax can't express.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum CalendarEntry {
+    Legacy(Vec<Event>),
+    Rich {
+        rrule: Option<String>,
+        events: Vec<Event>
+    }
 }
 
-impl Iterator for DateIter {
-    type Item = NaiveDate;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.finished || self.current > self.end {
-            return None;
+impl CalendarEntry {
+    fn events(&self) -> &[Event] {
+        match self {
+            CalendarEntry::Legacy(events) => events,
+            CalendarEntry::Rich { events, .. } => events
         }
+    }
 
-        let out = self.current;
-
-        match self.step {
-            DateStep::Days(0) => self.finished = true,
-            DateStep::Days(d) => {
-                let next = self.current.checked_add_signed(Duration::days(d));
-
-                match next {
-                    Some(d) => self.current = d,
-                    None => self.finished = true
-                }
-            },
-            DateStep::Months(m) => {
-                let next = self.current.checked_add_months(Months::new(m));
-
-                match next {
-                    Some(d) => self.current = d,
-                    None => self.finished = true
-                }
-            },
-            DateStep::Years(y) => {
-                let next = self.current.checked_add_months(Months::new(12 * y));
-
-                match next {
-                    Some(d) => self.current = d,
-                    None => self.finished = true
-                }
-            }
-        };
+    fn rrule(&self) -> Option<&str> {
+        match self {
+            CalendarEntry::Legacy(_) => None,
+            CalendarEntry::Rich { rrule, .. } => rrule.as_deref()
+        }
+    }
 
-        Some(out)
+    fn push(&mut self, event: Event) {
+        match self {
+            CalendarEntry::Legacy(events) => events.push(event),
+            CalendarEntry::Rich { events, .. } => events.push(event)
+        }
     }
 }
 
+type Calendar = BTreeMap<DateRange, CalendarEntry>;
+
 fn read_events(path: &str) -> Result<Calendar, Box<dyn Error>> {
     let file = File::open(path)?;
 
@@ -120,8 +111,9 @@ where
 
     let out: BTreeMap<DateRange, Vec<T>> = data
         .iter()
-        .map(|(date, events)| {
-            let values = events
+        .map(|(date, entry)| {
+            let values = entry
+                .events()
                 .iter()
                 .cloned()
                 .filter_map(&mut f)
@@ -136,7 +128,13 @@ where
     Ok(())
 }
 
-fn parse_date_range(date_range: &DateRange, now: DateTime<Utc>) -> Result<DateIter, Box<dyn Error>> {
+/// Parses the `YYYY(+yo)-MM(+mo)-DD(+do)` offset mini-language into the equivalent
+/// [`Rule`] (a single bounded occurrence when no `+offset` is present, otherwise a
+/// `COUNT`-less, `UNTIL`-bounded daily/monthly/yearly rule), so old `events.json` files
+/// keep building unchanged once translated into the new recurrence model. An empty
+/// `+offset` marker is resolved against `horizon` (the build's "now", or an agenda's
+/// window end), so callers control how far a bare `+` is allowed to project forward.
+fn translate_legacy_range(date_range: &DateRange, horizon: NaiveDate) -> Result<(NaiveDate, Rule), Box<dyn Error>> {
     let re = Regex::new(
         r"^(?P<y>\d{4})(?:\+(?P<yo>\d*))?-(?P<m>\d{2})(?:\+(?P<mo>\d*))?-(?P<d>\d{2})(?:\+(?P<do>\d*))?$"
     )?;
@@ -153,7 +151,7 @@ fn parse_date_range(date_range: &DateRange, now: DateTime<Utc>) -> Result<DateIt
 
     let years_offset: u32 = caps.name("yo")
         .map(|m| if m.as_str().is_empty() {
-            let diff = now.year() - start.year();
+            let diff = horizon.year() - start.year();
 
             if diff < 0 { 0 } else { diff as u32 }
         } else {
@@ -163,7 +161,7 @@ fn parse_date_range(date_range: &DateRange, now: DateTime<Utc>) -> Result<DateIt
 
     let months_offset: u32 = caps.name("mo")
         .map(|m| if m.as_str().is_empty() {
-            let diff = now.month() as i32 - start.month() as i32;
+            let diff = horizon.month() as i32 - start.month() as i32;
 
             if diff < 0 { 0 } else { diff as u32 }
         } else {
@@ -173,7 +171,7 @@ fn parse_date_range(date_range: &DateRange, now: DateTime<Utc>) -> Result<DateIt
 
     let days_offset: i64 = caps.name("do")
         .map(|m| if m.as_str().is_empty() {
-            let diff = now.day() as i64 - start.day() as i64;
+            let diff = horizon.day() as i64 - start.day() as i64;
 
             if diff < 0 { 0 } else { diff }
         } else {
@@ -187,23 +185,52 @@ fn parse_date_range(date_range: &DateRange, now: DateTime<Utc>) -> Result<DateIt
         end = end.checked_add_months(Months::new(12 * years_offset)).ok_or("invalid end date(y)")?;
     }
     if months_offset > 0 {
-        end = end.checked_add_months(Months::new(years_offset)).ok_or("invalid end date(m)")?;
+        end = end.checked_add_months(Months::new(months_offset)).ok_or("invalid end date(m)")?;
     }
     if days_offset > 0 {
         end = end.checked_add_signed(Duration::days(days_offset)).ok_or("invalid end date(d)")?;
     }
 
-    let step = if days_offset > 0 {
-        DateStep::Days(1)
+    let rule = if days_offset > 0 {
+        Rule { freq: Freq::Daily, interval: 1, count: None, until: Some(end), by_day: vec![] }
     } else if months_offset > 0 {
-        DateStep::Months(1)
+        Rule { freq: Freq::Monthly, interval: 1, count: None, until: Some(end), by_day: vec![] }
     } else if years_offset > 0 {
-        DateStep::Years(1)
+        Rule { freq: Freq::Yearly, interval: 1, count: None, until: Some(end), by_day: vec![] }
     } else {
-        DateStep::Days(0)
+        Rule { freq: Freq::Daily, interval: 1, count: Some(1), until: None, by_day: vec![] }
     };
 
-    Ok(DateIter { current: start, end: end, step: step, finished: false })
+    Ok((start, rule))
+}
+
+/// Builds the anchor date and recurrence for a calendar entry: an explicit `rrule` field
+/// takes priority over the date-range key's `+offset` syntax. A rule with neither `COUNT`
+/// nor `UNTIL` recurs forever, same as a real `RRULE` — callers decide how far to unroll it,
+/// and pass `horizon` through to [`translate_legacy_range`] for resolving a bare `+` marker.
+fn parse_date_range(date_range: &DateRange, entry: &CalendarEntry, horizon: NaiveDate) -> Result<(NaiveDate, Rule), Box<dyn Error>> {
+    match entry.rrule() {
+        Some(rrule) => {
+            let anchor_re = Regex::new(r"^(?P<y>\d{4})\+?\d*-(?P<m>\d{2})\+?\d*-(?P<d>\d{2})\+?\d*$")?;
+            let caps = anchor_re.captures(date_range).ok_or("invalid date range")?;
+
+            let start = NaiveDate::from_ymd_opt(
+                caps["y"].parse()?,
+                caps["m"].parse()?,
+                caps["d"].parse()?
+            ).unwrap();
+
+            Ok((start, parse_rrule(rrule)?))
+        },
+        None => translate_legacy_range(date_range, horizon)
+    }
+}
+
+/// How far to unroll a rule that has neither `COUNT` nor `UNTIL` (a real open-ended
+/// `RRULE`): one year past `now`, which comfortably covers anything the static build or
+/// an agenda window would ask for.
+pub(crate) fn forward_horizon(now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive() + Duration::days(366)
 }
 
 fn format_birthday(name: &String, age: i32) -> String {
@@ -219,66 +246,194 @@ fn format_birthday(name: &String, age: i32) -> String {
     }
 }
 
-fn format_comp(id: &CompID) -> String {
-    format!("<h1><a href=\"https://www.worldcubeassociation.org/competitions/{}\">{0}</a></h1>", id)
+fn format_comp(id: &CompID, info: Option<&CompInfo>) -> String {
+    match info {
+        Some(info) => format!(
+            "<h1><a href=\"https://www.worldcubeassociation.org/competitions/{id}\">{}</a></h1> <p>{} - {} - {}, {}</p>",
+            info.name, info.start_date, info.end_date, info.city, info.venue
+        ),
+        None => format!("<h1><a href=\"https://www.worldcubeassociation.org/competitions/{0}\">{0}</a></h1>", id)
+    }
 }
 
-fn format_transit(transit: &Transit) -> String {
-    "bleh :p".to_string()
+fn format_transit(transit: &Transit, gtfs: &Gtfs, now: DateTime<Utc>) -> String {
+    match transit {
+        Transit::Walk { from, to } => format!("<h1>Walk from {from} to {to}</h1>"),
+        Transit::Plane { from, to } => format!("<h1>Flight from {from} to {to}</h1>"),
+        Transit::Bus { from, to, info } => format_scheduled_transit("Bus", from, to, info, gtfs, now),
+        Transit::Metro { from, to, info } => format_scheduled_transit("Metro", from, to, info, gtfs, now),
+        Transit::Train { from, to, info } => format_scheduled_transit("Train", from, to, info, gtfs, now)
+    }
+}
+
+fn format_scheduled_transit(mode: &str, from: &Place, to: &Place, info: &str, gtfs: &Gtfs, now: DateTime<Utc>) -> String {
+    let from_name = gtfs.resolve(from).map(|s| s.name.as_str()).unwrap_or(from);
+    let to_name = gtfs.resolve(to).map(|s| s.name.as_str()).unwrap_or(to);
+
+    let time_of_day = format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second());
+
+    let schedule = gtfs.next_departure(from, &time_of_day)
+        .map(|(time, route)| format!(" - next {route} departs {time}"))
+        .unwrap_or_default();
+
+    format!("<h1>{mode} from {from_name} to {to_name} ({info}){schedule}</h1>")
+}
+
+/// Day-to-day calendar tool, in the spirit of khaleesi's action set: `build` regenerates
+/// the static site (the default, so a bare `calendar` invocation behaves as before),
+/// `agenda` lists what's coming up, and `add` appends a new event.
+#[derive(Parser)]
+#[command(name = "calendar")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rebuild docs/ from docs/events.json
+    Build {
+        /// Fetch missing competition metadata from the WCA API (offline builds skip this
+        /// and fall back to cached or plain-id rendering)
+        #[arg(long)]
+        fetch_comps: bool
+    },
+    /// List events in an upcoming window
+    Agenda {
+        /// Window to show (ignored when --days is given)
+        #[arg(value_enum)]
+        window: Option<AgendaWindow>,
+        /// Number of days to look ahead, overriding the window
+        #[arg(long)]
+        days: Option<i64>,
+        /// Reference date (defaults to today)
+        #[arg(long)]
+        date: Option<NaiveDate>
+    },
+    /// Append a new event under a date-range key
+    Add {
+        date_range: DateRange,
+        #[command(subcommand)]
+        event: AddEvent
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AgendaWindow {
+    Day,
+    Week,
+    Month
+}
+
+#[derive(Subcommand)]
+enum AddEvent {
+    Birthday { name: Name },
+    Comp { id: CompID },
+    Walk { from: Place, to: Place },
+    Bus { from: Place, to: Place, info: String },
+    Metro { from: Place, to: Place, info: String },
+    Train { from: Place, to: Place, info: String },
+    Plane { from: Place, to: Place }
+}
+
+impl From<AddEvent> for Event {
+    fn from(event: AddEvent) -> Event {
+        match event {
+            AddEvent::Birthday { name } => Event::Birthday(name),
+            AddEvent::Comp { id } => Event::Comp(id),
+            AddEvent::Walk { from, to } => Event::Transit(Transit::Walk { from, to }),
+            AddEvent::Bus { from, to, info } => Event::Transit(Transit::Bus { from, to, info }),
+            AddEvent::Metro { from, to, info } => Event::Transit(Transit::Metro { from, to, info }),
+            AddEvent::Train { from, to, info } => Event::Transit(Transit::Train { from, to, info }),
+            AddEvent::Plane { from, to } => Event::Transit(Transit::Plane { from, to })
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command.unwrap_or(Command::Build { fetch_comps: false }) {
+        Command::Build { fetch_comps } => build(fetch_comps),
+        Command::Agenda { window, days, date } => agenda(window, days, date),
+        Command::Add { date_range, event } => add(date_range, event.into())
+    }
+}
+
+fn build(fetch_comps: bool) -> Result<(), Box<dyn Error>> {
     let calendar: Calendar = read_events("docs/events.json")?;
+    let gtfs = Gtfs::load("gtfs")?;
+    let mut comp_cache = wca::load_cache("docs/comps_cache.json")?;
 
     let now: DateTime<Utc> = Utc::now();
 
+    // Grouped by the date each event should actually render on: a Comp with cached WCA
+    // metadata renders on its real start date rather than the date-range key it was filed
+    // under, so this can differ from the `virtual_date` it was unrolled at.
+    let mut by_day: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+
+    let horizon = forward_horizon(now);
+
+    for (date, entry) in &calendar {
+        let events = entry.events();
+        let (start, rule) = parse_date_range(date, entry, now.date_naive())?;
+
+        // A rule with no COUNT/UNTIL recurs forever; only that open-ended case gets capped
+        // at `horizon`. Bounded rules (an explicit COUNT/UNTIL, or a legacy `+offset`) run
+        // to completion as-is, including occurrences that land in the future.
+        let unbounded = rule.count.is_none() && rule.until.is_none();
+        let mut range = iter_rule(start, rule);
+
+        for virtual_date in (&mut range).take_while(|d| !unbounded || *d <= horizon) {
+            for event in events {
+                let (render_date, html) = match event {
+                    Event::Birthday(name) => (virtual_date, format_birthday(name, virtual_date.year() - start.year())),
+                    Event::Comp(id) => {
+                        let info = wca::resolve(&mut comp_cache, id, fetch_comps);
+                        let render_date = info.as_ref().map(|i| i.start_date).unwrap_or(virtual_date);
+
+                        (render_date, format_comp(id, info.as_ref()))
+                    },
+                    Event::Transit(transit) => (virtual_date, format_transit(transit, &gtfs, now))
+                };
+
+                by_day.entry(render_date).or_default().push(html);
+            }
+        }
+    }
+
+    wca::save_cache(&comp_cache, "docs/comps_cache.json")?;
+
     let mut index = String::new();
 
-    for (date, events) in &calendar {
-        let mut range = parse_date_range(&date, now)?;
-        let start = range.current;
+    for (virtual_date, divs) in &by_day {
+        let y = format!("{:04}", virtual_date.year());
+        let m = format!("{:02}", virtual_date.month());
+        let d = format!("{:02}", virtual_date.day());
 
-        for virtual_date in &mut range {
-            let y = format!("{:04}", virtual_date.year());
-            let m = format!("{:02}", virtual_date.month());
-            let d = format!("{:02}", virtual_date.day());
+        let ymd = format!("{y}/{m}/{d}");
 
-            let ymd = format!("{y}/{m}/{d}");
+        index.push_str(
+            &format!("<h1><a href=\"{}\">{0}</a></h1>", ymd)
+        );
 
-            index.push_str(
-                &format!("<h1><a href=\"{}\">{0}</a></h1>", ymd)
-            );
+        create_dir_all(
+            format!("docs/{ymd}")
+        )?;
 
-            create_dir_all(
-                format!("docs/{ymd}")
-            )?;
+        let divs = divs.join("");
 
-            let divs = events
-                .iter()
-                .map(|e|
-                    match e {
-                        Event::Birthday(name) => format_birthday(name, virtual_date.year() - start.year()),
-                        Event::Comp(id) => format_comp(id),
-                        Event::Transit(transit) => format_transit(transit)
-                    }
-                )
-                .collect::<Vec<_>>()
-                .join("");
-
-            let index = format!(
-                include_str!("index.html"),
-                format!("{ymd} - calendar"),
-                divs
-            );
-
-            let file = File::create(
-                format!("docs/{ymd}/index.html")
-            )?;
-
-            let mut writer = BufWriter::new(file);
-
-            write!(writer, "{}", index)?;
-        }
+        let index = format!(
+            include_str!("index.html"),
+            format!("{ymd} - calendar"),
+            divs
+        );
+
+        let file = File::create(
+            format!("docs/{ymd}/index.html")
+        )?;
+
+        let mut writer = BufWriter::new(file);
+
+        write!(writer, "{}", index)?;
     }
 
     let index = format!(
@@ -319,5 +474,113 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     })?;
 
+    write_ics(&calendar, "docs/calendar.ics", now)?;
+
+    Ok(())
+}
+
+/// Unrolls every calendar entry's recurrence into `(NaiveDate, Event)` pairs, merges them
+/// into one globally date-sorted stream, keeps only those falling inside the window
+/// starting at `date` (or today), and prints them grouped by day in plain text.
+fn agenda(window: Option<AgendaWindow>, days: Option<i64>, date: Option<NaiveDate>) -> Result<(), Box<dyn Error>> {
+    let calendar: Calendar = read_events("docs/events.json")?;
+    let gtfs = Gtfs::load("gtfs")?;
+    let mut comp_cache = wca::load_cache("docs/comps_cache.json")?;
+
+    let now = Utc::now();
+    let reference = date.unwrap_or_else(|| now.date_naive());
+
+    let span_days = days.unwrap_or(match window.unwrap_or(AgendaWindow::Week) {
+        AgendaWindow::Day => 1,
+        AgendaWindow::Week => 7,
+        AgendaWindow::Month => 30
+    });
+
+    let window_end = reference
+        .checked_add_signed(Duration::days(span_days))
+        .ok_or("window out of range")?;
+
+    let mut by_day: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+
+    for (date_range, entry) in &calendar {
+        let events = entry.events();
+        // Resolve a bare `+offset` marker against the window's end, not `now`'s calendar
+        // year, so a recurring legacy entry whose next occurrence crosses a year boundary
+        // (e.g. a Jan 1 birthday found from an agenda run on Dec 28) still projects forward
+        // into the window instead of getting capped at the current year.
+        let (start, rule) = parse_date_range(date_range, entry, window_end)?;
+        let range = iter_rule(start, rule);
+
+        for virtual_date in range.take_while(|d| *d < window_end) {
+            if virtual_date < reference {
+                continue;
+            }
+
+            for event in events {
+                let text = match event {
+                    Event::Birthday(name) => format_birthday(name, virtual_date.year() - start.year()),
+                    Event::Comp(id) => format_comp(id, wca::resolve(&mut comp_cache, id, false).as_ref()),
+                    Event::Transit(transit) => format_transit(transit, &gtfs, now)
+                };
+
+                by_day.entry(virtual_date).or_default().push(strip_tags(&text));
+            }
+        }
+    }
+
+    for (day, lines) in by_day {
+        println!("{day}");
+        for line in lines {
+            println!("  {line}");
+        }
+    }
+
     Ok(())
 }
+
+/// Appends `event` under `date_range`, creating the entry if it doesn't exist yet, and
+/// persists the result via `write_events`.
+fn add(date_range: DateRange, event: Event) -> Result<(), Box<dyn Error>> {
+    let mut calendar: Calendar = read_events("docs/events.json")?;
+
+    calendar
+        .entry(date_range)
+        .or_insert_with(|| CalendarEntry::Legacy(Vec::new()))
+        .push(event);
+
+    write_events(&calendar, "docs/events.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_range_with_no_offsets_is_a_single_occurrence() {
+        let (start, rule) = translate_legacy_range(&"2026-03-14".to_string(), NaiveDate::from_ymd_opt(2026, 7, 1).unwrap()).unwrap();
+
+        assert_eq!(start, NaiveDate::from_ymd_opt(2026, 3, 14).unwrap());
+        assert_eq!(rule.count, Some(1));
+        assert_eq!(rule.until, None);
+    }
+
+    #[test]
+    fn legacy_range_with_bare_year_offset_resolves_against_horizon() {
+        let horizon = NaiveDate::from_ymd_opt(2029, 1, 1).unwrap();
+        let (start, rule) = translate_legacy_range(&"2026+-03-14".to_string(), horizon).unwrap();
+
+        assert_eq!(start, NaiveDate::from_ymd_opt(2026, 3, 14).unwrap());
+        assert_eq!(rule.freq, Freq::Yearly);
+        assert_eq!(rule.until, Some(NaiveDate::from_ymd_opt(2029, 3, 14).unwrap()));
+    }
+
+    #[test]
+    fn legacy_range_with_explicit_months_offset_uses_months_not_years() {
+        // Regression check for the original `Months::new(years_offset)` bug in the months
+        // branch: a `+3` months offset must add 3 months, not 3 years.
+        let (_, rule) = translate_legacy_range(&"2026-01+3-01".to_string(), NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()).unwrap();
+
+        assert_eq!(rule.freq, Freq::Monthly);
+        assert_eq!(rule.until, Some(NaiveDate::from_ymd_opt(2026, 4, 1).unwrap()));
+    }
+}
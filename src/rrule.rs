@@ -0,0 +1,238 @@
+use std::error::Error;
+
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly
+}
+
+/// A parsed `RRULE`: `FREQ=DAILY|WEEKLY|MONTHLY|YEARLY`, `INTERVAL=n`, a terminating
+/// `COUNT=n` or `UNTIL=YYYYMMDD`, and (for weekly/monthly rules) `BYDAY=MO,TU,...`.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub freq: Freq,
+    pub interval: u32,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDate>,
+    pub by_day: Vec<Weekday>
+}
+
+fn parse_weekday(code: &str) -> Result<Weekday, Box<dyn Error>> {
+    Ok(match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        other => return Err(format!("invalid BYDAY code: {other}").into())
+    })
+}
+
+/// Parses an `rrule` field value, e.g. `"FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH"`.
+pub fn parse_rrule(s: &str) -> Result<Rule, Box<dyn Error>> {
+    let mut freq = None;
+    let mut interval = 1;
+    let mut count = None;
+    let mut until = None;
+    let mut by_day = Vec::new();
+
+    for part in s.split(';') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        let (key, value) = part.split_once('=').ok_or("malformed rrule part")?;
+
+        match key {
+            "FREQ" => freq = Some(match value {
+                "DAILY" => Freq::Daily,
+                "WEEKLY" => Freq::Weekly,
+                "MONTHLY" => Freq::Monthly,
+                "YEARLY" => Freq::Yearly,
+                other => return Err(format!("unsupported FREQ: {other}").into())
+            }),
+            "INTERVAL" => interval = value.parse()?,
+            "COUNT" => count = Some(value.parse()?),
+            "UNTIL" => until = Some(NaiveDate::parse_from_str(value, "%Y%m%d")?),
+            "BYDAY" => by_day = value
+                .split(',')
+                .map(parse_weekday)
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {}
+        }
+    }
+
+    Ok(Rule {
+        freq: freq.ok_or("rrule is missing FREQ")?,
+        interval,
+        count,
+        until,
+        by_day
+    })
+}
+
+fn advance(date: NaiveDate, freq: Freq, periods: u32) -> Option<NaiveDate> {
+    match freq {
+        Freq::Daily => date.checked_add_signed(Duration::days(periods as i64)),
+        Freq::Weekly => date.checked_add_signed(Duration::days(7 * periods as i64)),
+        Freq::Monthly => date.checked_add_months(Months::new(periods)),
+        Freq::Yearly => date.checked_add_months(Months::new(12 * periods))
+    }
+}
+
+/// Expands a single period (the week or month containing `period_start`) into every date
+/// matching `by_day`, dropping anything before `anchor`. Periods with no `BYDAY` (or
+/// daily/yearly periods, where `BYDAY` doesn't apply) just yield `period_start` itself.
+fn expand_period(period_start: NaiveDate, freq: Freq, by_day: &[Weekday], anchor: NaiveDate) -> Vec<NaiveDate> {
+    if by_day.is_empty() || !matches!(freq, Freq::Weekly | Freq::Monthly) {
+        return vec![period_start];
+    }
+
+    let mut dates = match freq {
+        Freq::Weekly => {
+            let week_start = period_start - Duration::days(period_start.weekday().num_days_from_monday() as i64);
+
+            by_day
+                .iter()
+                .map(|day| week_start + Duration::days(day.num_days_from_monday() as i64))
+                .collect::<Vec<_>>()
+        },
+        Freq::Monthly => {
+            let (year, month) = (period_start.year(), period_start.month());
+            let mut dates = Vec::new();
+            let mut day = 1;
+
+            while let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+                if by_day.contains(&date.weekday()) {
+                    dates.push(date);
+                }
+                day += 1;
+            }
+
+            dates
+        },
+        _ => unreachable!()
+    };
+
+    dates.retain(|d| *d >= anchor);
+    dates.sort();
+
+    dates
+}
+
+/// Iterates the concrete dates a [`Rule`] expands to, starting from `anchor`, stopping once
+/// `COUNT` occurrences are emitted or `UNTIL` is passed. With neither bound set the rule
+/// recurs forever, as a real `RRULE` does — callers that need a finite build (the static
+/// site, an agenda window) are responsible for truncating the iterator themselves.
+#[derive(Debug, Clone)]
+pub struct RRuleIter {
+    anchor: NaiveDate,
+    rule: Rule,
+    period: u32,
+    queue: Vec<NaiveDate>,
+    emitted: u32,
+    finished: bool
+}
+
+pub fn iter_rule(anchor: NaiveDate, rule: Rule) -> RRuleIter {
+    RRuleIter { anchor, rule, period: 0, queue: Vec::new(), emitted: 0, finished: false }
+}
+
+impl Iterator for RRuleIter {
+    type Item = NaiveDate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.finished {
+                return None;
+            }
+
+            if self.queue.is_empty() {
+                let period_start = match advance(self.anchor, self.rule.freq, self.rule.interval * self.period) {
+                    Some(date) => date,
+                    None => { self.finished = true; return None; }
+                };
+
+                self.queue = expand_period(period_start, self.rule.freq, &self.rule.by_day, self.anchor);
+                self.queue.reverse();
+                self.period += 1;
+
+                if self.queue.is_empty() {
+                    continue;
+                }
+            }
+
+            let date = self.queue.pop().unwrap();
+
+            if let Some(until) = self.rule.until {
+                if date > until {
+                    self.finished = true;
+                    return None;
+                }
+            }
+
+            self.emitted += 1;
+            if let Some(count) = self.rule.count {
+                if self.emitted >= count {
+                    self.finished = true;
+                }
+            }
+
+            return Some(date);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn biweekly_advances_by_two_weeks() {
+        let rule = parse_rrule("FREQ=WEEKLY;INTERVAL=2").unwrap();
+        let dates: Vec<_> = iter_rule(date(2026, 1, 5), rule).take(3).collect();
+
+        assert_eq!(dates, vec![date(2026, 1, 5), date(2026, 1, 19), date(2026, 2, 2)]);
+    }
+
+    #[test]
+    fn by_day_expands_to_matching_weekdays_in_anchor_period() {
+        let rule = parse_rrule("FREQ=WEEKLY;BYDAY=TU,TH").unwrap();
+        let dates: Vec<_> = iter_rule(date(2026, 1, 6), rule).take(4).collect();
+
+        // 2026-01-06 is a Tuesday; the anchor week also contains Thursday the 8th.
+        assert_eq!(dates, vec![date(2026, 1, 6), date(2026, 1, 8), date(2026, 1, 13), date(2026, 1, 15)]);
+    }
+
+    #[test]
+    fn count_stops_before_until_is_reached() {
+        let rule = parse_rrule("FREQ=DAILY;COUNT=3;UNTIL=20261231").unwrap();
+        let dates: Vec<_> = iter_rule(date(2026, 1, 1), rule).collect();
+
+        assert_eq!(dates, vec![date(2026, 1, 1), date(2026, 1, 2), date(2026, 1, 3)]);
+    }
+
+    #[test]
+    fn until_stops_before_count_is_reached() {
+        let rule = parse_rrule("FREQ=DAILY;COUNT=30;UNTIL=20260103").unwrap();
+        let dates: Vec<_> = iter_rule(date(2026, 1, 1), rule).collect();
+
+        assert_eq!(dates, vec![date(2026, 1, 1), date(2026, 1, 2), date(2026, 1, 3)]);
+    }
+
+    #[test]
+    fn rrule_rejects_unsupported_freq() {
+        assert!(parse_rrule("FREQ=HOURLY").is_err());
+    }
+}
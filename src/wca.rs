@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::CompID;
+
+/// A competition's metadata as cached from the WCA API: name, date span, city and venue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompInfo {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub city: String,
+    pub venue: String
+}
+
+pub type CompCache = BTreeMap<CompID, CompInfo>;
+
+#[derive(Deserialize)]
+struct WcaCompetition {
+    name: String,
+    city: String,
+    venue: String,
+    start_date: String,
+    end_date: String
+}
+
+/// Loads the local `docs/comps_cache.json`, or an empty cache if it doesn't exist yet.
+pub fn load_cache(path: &str) -> Result<CompCache, Box<dyn Error>> {
+    match File::open(path) {
+        Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(CompCache::new()),
+        Err(e) => Err(e.into())
+    }
+}
+
+pub fn save_cache(cache: &CompCache, path: &str) -> Result<(), Box<dyn Error>> {
+    let file = File::create(path)?;
+
+    Ok(serde_json::to_writer(BufWriter::new(file), cache)?)
+}
+
+fn fetch(id: &CompID) -> Result<CompInfo, Box<dyn Error>> {
+    let url = format!("https://www.worldcubeassociation.org/api/v0/competitions/{id}");
+
+    let competition: WcaCompetition = reqwest::blocking::get(url)?.json()?;
+
+    Ok(CompInfo {
+        name: competition.name,
+        start_date: NaiveDate::parse_from_str(&competition.start_date, "%Y-%m-%d")?,
+        end_date: NaiveDate::parse_from_str(&competition.end_date, "%Y-%m-%d")?,
+        city: competition.city,
+        venue: competition.venue
+    })
+}
+
+/// Resolves a competition's metadata, consulting `cache` before ever going to the network.
+/// When `allow_fetch` is false (offline builds) or the fetch fails, falls back to `None` so
+/// callers can render the plain-id version instead.
+pub fn resolve(cache: &mut CompCache, id: &CompID, allow_fetch: bool) -> Option<CompInfo> {
+    if let Some(info) = cache.get(id) {
+        return Some(info.clone());
+    }
+
+    if !allow_fetch {
+        return None;
+    }
+
+    let info = fetch(id).ok()?;
+    cache.insert(id.clone(), info.clone());
+
+    Some(info)
+}